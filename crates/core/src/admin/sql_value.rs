@@ -1,5 +1,7 @@
 use base64::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use trailbase_sqlite::Value;
 use ts_rs::TS;
 
@@ -9,6 +11,40 @@ pub enum DecodeError {
   Base64(#[from] base64::DecodeError),
   #[error("Hex")]
   Hex,
+  #[error("unknown blob codec: {0}")]
+  UnknownCodec(String),
+  #[error("decompression failed")]
+  Decompress,
+}
+
+/// How a blob column should be encoded when read back, requested per-call by the client
+/// instead of always getting [`Blob::Base64UrlSafe`].
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobReadEncoding {
+  Array,
+  Hex,
+  #[default]
+  Base64UrlSafe,
+}
+
+/// Codec tags used by [`Blob::Compressed`]. Kept as plain strings on the wire (like
+/// `Hex`/`Base64UrlSafe`) rather than a typed enum so an unrecognized codec from a newer client
+/// or server surfaces as [`DecodeError::UnknownCodec`] instead of a hard deserialization failure.
+/// We only ever *write* `GZIP_CODEC`; `ZSTD_CODEC` is accepted on read for forward-compatibility
+/// with blobs written by a future/other codec, not currently produced by this server.
+const GZIP_CODEC: &str = "gzip";
+const ZSTD_CODEC: &str = "zstd";
+
+/// Blobs at or above this size (in bytes) are compressed by `From<Value> for SqlValue`.
+/// Defaults to `usize::MAX`, i.e. disabled, until explicitly configured.
+static BLOB_COMPRESSION_THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Configures the size threshold at or above which `From<Value> for SqlValue` compresses blob columns
+/// as [`Blob::Compressed`] instead of encoding them raw.
+pub fn set_blob_compression_threshold(bytes: usize) {
+  BLOB_COMPRESSION_THRESHOLD.store(bytes, Ordering::Relaxed);
 }
 
 #[derive(Debug, Deserialize, Serialize, TS)]
@@ -18,6 +54,9 @@ pub enum Blob {
   /// NOTE: default for reads, given it has best compression-ratio.
   Base64UrlSafe(String),
   Hex(String),
+  /// Gzip/zstd-compressed bytes, base64-encoded. Used for large binary columns (images,
+  /// embeddings) where base64 alone still bloats the response.
+  Compressed { codec: String, data: String },
 }
 
 /// Mimic's rusqlite's Value but is JS/JSON serializable and supports multiple blob encodings..
@@ -44,6 +83,9 @@ impl TryFrom<SqlValue> for Value {
         Blob::Array(v) => Value::Blob(v),
         Blob::Base64UrlSafe(v) => Value::Blob(BASE64_URL_SAFE.decode(v)?),
         Blob::Hex(v) => Value::Blob(decode_hex(&v)?),
+        Blob::Compressed { codec, data } => {
+          Value::Blob(decompress(&codec, &BASE64_URL_SAFE.decode(data)?)?)
+        }
       },
     });
   }
@@ -56,7 +98,7 @@ impl From<Value> for SqlValue {
       Value::Integer(v) => SqlValue::Integer(v),
       Value::Real(v) => SqlValue::Real(v),
       Value::Text(v) => SqlValue::Text(v),
-      Value::Blob(v) => SqlValue::Blob(Blob::Base64UrlSafe(BASE64_URL_SAFE.encode(v))),
+      Value::Blob(v) => SqlValue::Blob(encode_blob(v, BlobReadEncoding::Base64UrlSafe)),
     };
   }
 }
@@ -68,9 +110,82 @@ impl From<&Value> for SqlValue {
       Value::Integer(v) => SqlValue::Integer(*v),
       Value::Real(v) => SqlValue::Real(*v),
       Value::Text(v) => SqlValue::Text(v.clone()),
-      Value::Blob(v) => SqlValue::Blob(Blob::Base64UrlSafe(BASE64_URL_SAFE.encode(v))),
+      Value::Blob(v) => SqlValue::Blob(encode_blob(v.clone(), BlobReadEncoding::Base64UrlSafe)),
+    };
+  }
+}
+
+/// Encodes a blob column for the response, matching the client-requested `encoding` instead of
+/// the default base64. Blobs at or above the compression threshold come back as
+/// [`Blob::Compressed`] whatever `encoding` is requested.
+pub fn sql_value_from_value(value: Value, encoding: BlobReadEncoding) -> SqlValue {
+  return match value {
+    Value::Null => SqlValue::Null,
+    Value::Integer(v) => SqlValue::Integer(v),
+    Value::Real(v) => SqlValue::Real(v),
+    Value::Text(v) => SqlValue::Text(v),
+    Value::Blob(v) => SqlValue::Blob(encode_blob(v, encoding)),
+  };
+}
+
+/// Shared by both the blanket `From<Value>` impls and `sql_value_from_value`: blobs at or above
+/// the configured compression threshold always come back as [`Blob::Compressed`] regardless of
+/// the requested `encoding`, since that threshold exists to bound response size; smaller blobs
+/// honor the caller's requested encoding.
+fn encode_blob(bytes: Vec<u8>, encoding: BlobReadEncoding) -> Blob {
+  let threshold = BLOB_COMPRESSION_THRESHOLD.load(Ordering::Relaxed);
+  if bytes.len() >= threshold {
+    return Blob::Compressed {
+      codec: GZIP_CODEC.to_string(),
+      data: BASE64_URL_SAFE.encode(compress_gzip(&bytes)),
     };
   }
+
+  return match encoding {
+    BlobReadEncoding::Array => Blob::Array(bytes),
+    BlobReadEncoding::Hex => Blob::Hex(encode_hex(&bytes)),
+    BlobReadEncoding::Base64UrlSafe => Blob::Base64UrlSafe(BASE64_URL_SAFE.encode(bytes)),
+  };
+}
+
+fn compress_gzip(bytes: &[u8]) -> Vec<u8> {
+  let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+  encoder.write_all(bytes).expect("in-memory write");
+  return encoder.finish().expect("in-memory write");
+}
+
+/// Hard cap on decompressed blob size, to bound the damage a small, deliberately-crafted
+/// `Blob::Compressed` payload (a zip/zstd bomb) can do before the value is ever used.
+const MAX_DECOMPRESSED_BLOB_BYTES: u64 = 128 * 1024 * 1024;
+
+fn decompress(codec: &str, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+  return match codec {
+    GZIP_CODEC => read_capped(flate2::read::GzDecoder::new(bytes)),
+    ZSTD_CODEC => read_capped(
+      zstd::stream::read::Decoder::new(bytes).map_err(|_| DecodeError::Decompress)?,
+    ),
+    other => Err(DecodeError::UnknownCodec(other.to_string())),
+  };
+}
+
+/// Reads `reader` to completion, erroring out instead of allocating past
+/// `MAX_DECOMPRESSED_BLOB_BYTES`.
+fn read_capped(reader: impl Read) -> Result<Vec<u8>, DecodeError> {
+  let mut out = Vec::new();
+  let read = reader
+    .take(MAX_DECOMPRESSED_BLOB_BYTES + 1)
+    .read_to_end(&mut out)
+    .map_err(|_| DecodeError::Decompress)?;
+
+  if read as u64 > MAX_DECOMPRESSED_BLOB_BYTES {
+    return Err(DecodeError::Decompress);
+  }
+
+  return Ok(out);
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+  return bytes.iter().map(|b| format!("{b:02x}")).collect();
 }
 
 fn decode_hex(s: &str) -> Result<Vec<u8>, DecodeError> {
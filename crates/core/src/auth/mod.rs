@@ -0,0 +1,13 @@
+pub mod oauth;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+  #[error("unauthorized")]
+  Unauthorized,
+  #[error("bad request: {0}")]
+  BadRequest(String),
+  #[error("failed dependency: {0}")]
+  FailedDependency(Box<dyn std::error::Error + Send + Sync>),
+  #[error("internal: {0}")]
+  Internal(Box<dyn std::error::Error + Send + Sync>),
+}
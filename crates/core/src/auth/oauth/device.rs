@@ -0,0 +1,170 @@
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+use crate::auth::AuthError;
+use crate::auth::oauth::{OAuthProvider, OAuthUser};
+
+/// OAuth 2.0 Device Authorization Grant (RFC 8628), for CLIs and other devices that can't open
+/// a browser to complete the usual redirect-based flow. The caller requests a device code,
+/// shows the `user_code`/`verification_uri` to the user on whatever display it has, then polls
+/// the token endpoint until the user completes the flow elsewhere (e.g. on their phone).
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAuthorization {
+  pub device_code: String,
+  pub user_code: String,
+  pub verification_uri: String,
+  pub verification_uri_complete: Option<String>,
+  pub expires_in: u64,
+  #[serde(default)]
+  interval: Option<u64>,
+}
+
+impl DeviceAuthorization {
+  pub fn interval(&self) -> Duration {
+    return self
+      .interval
+      .map(Duration::from_secs)
+      .unwrap_or(DEFAULT_POLL_INTERVAL);
+  }
+}
+
+/// Kicks off the device flow by requesting a `device_code`/`user_code` pair. The returned
+/// [`DeviceAuthorization`] is what gets shown to the user (e.g. "go to `verification_uri` and
+/// enter `user_code`").
+pub(crate) async fn request_device_authorization(
+  provider: &dyn OAuthProvider,
+) -> Result<DeviceAuthorization, AuthError> {
+  let settings = provider.settings().await?;
+  let Some(device_authorization_url) = settings.device_authorization_url else {
+    return Err(AuthError::BadRequest(format!(
+      "provider '{name}' does not support the device authorization grant",
+      name = provider.name()
+    )));
+  };
+
+  let response = reqwest::Client::new()
+    .post(device_authorization_url)
+    .form(&[
+      ("client_id", settings.client_id.as_str()),
+      ("scope", &provider.oauth_scopes().join(" ")),
+    ])
+    .send()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+  if !response.status().is_success() {
+    return Err(AuthError::FailedDependency(
+      format!("device authorization request failed: {}", response.status()).into(),
+    ));
+  }
+
+  return response
+    .json::<DeviceAuthorization>()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()));
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+  error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+  access_token: String,
+  id_token: Option<String>,
+}
+
+enum PollOutcome {
+  Pending,
+  SlowDown,
+  Success(TokenResponse),
+}
+
+async fn poll_once(token_url: &Url, client_id: &str, client_secret: &str, device_code: &str) -> Result<PollOutcome, AuthError> {
+  let response = reqwest::Client::new()
+    .post(token_url.clone())
+    .form(&[
+      ("client_id", client_id),
+      ("client_secret", client_secret),
+      ("device_code", device_code),
+      (
+        "grant_type",
+        "urn:ietf:params:oauth:grant-type:device_code",
+      ),
+    ])
+    .send()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+  if response.status().is_success() {
+    let token = response
+      .json::<TokenResponse>()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?;
+    return Ok(PollOutcome::Success(token));
+  }
+
+  let err = response
+    .json::<TokenErrorResponse>()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+  return match err.error.as_str() {
+    "authorization_pending" => Ok(PollOutcome::Pending),
+    "slow_down" => Ok(PollOutcome::SlowDown),
+    "expired_token" => Err(AuthError::BadRequest("device code expired".to_string())),
+    "access_denied" => Err(AuthError::Unauthorized),
+    other => Err(AuthError::FailedDependency(
+      format!("device authorization failed: {other}").into(),
+    )),
+  };
+}
+
+/// Polls the token endpoint until the user has completed the flow in their browser, honoring
+/// the `interval`/`slow_down`/`expired_token`/`access_denied` semantics of RFC 8628, then routes
+/// the resulting tokens through the same [`OAuthProvider::get_user`] path as the redirect-based
+/// flow and returns the resulting [`OAuthUser`].
+pub(crate) async fn poll_for_user(
+  provider: Arc<dyn OAuthProvider>,
+  authorization: &DeviceAuthorization,
+) -> Result<OAuthUser, AuthError> {
+  let settings = provider.settings().await?;
+  let mut interval = authorization.interval();
+  let deadline = tokio::time::Instant::now() + Duration::from_secs(authorization.expires_in);
+
+  loop {
+    if tokio::time::Instant::now() >= deadline {
+      return Err(AuthError::BadRequest("device code expired".to_string()));
+    }
+
+    tokio::time::sleep(interval).await;
+
+    match poll_once(
+      &settings.token_url,
+      &settings.client_id,
+      &settings.client_secret,
+      &authorization.device_code,
+    )
+    .await?
+    {
+      PollOutcome::Pending => continue,
+      PollOutcome::SlowDown => {
+        interval += SLOW_DOWN_INCREMENT;
+        continue;
+      }
+      PollOutcome::Success(token) => {
+        // The device flow has no redirect step to stash a nonce in, so there's nothing to
+        // validate it against.
+        return provider
+          .get_user(token.access_token, token.id_token, None)
+          .await;
+      }
+    }
+  }
+}
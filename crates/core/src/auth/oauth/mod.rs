@@ -0,0 +1,55 @@
+pub mod device;
+pub mod providers;
+
+use async_trait::async_trait;
+use url::Url;
+
+use crate::auth::AuthError;
+use crate::config::proto::OAuthProviderId;
+
+/// Endpoints and credentials needed to drive an OAuth flow against a given provider. `auth_url`
+/// and `token_url` are always required; `device_authorization_url` is only populated for
+/// providers that support the device authorization grant (RFC 8628).
+#[derive(Debug, Clone)]
+pub struct OAuthClientSettings {
+  pub auth_url: Url,
+  pub token_url: Url,
+  pub device_authorization_url: Option<Url>,
+  pub client_id: String,
+  pub client_secret: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuthUser {
+  pub provider_user_id: String,
+  pub provider_id: OAuthProviderId,
+  pub email: String,
+  pub verified: bool,
+  pub avatar: Option<String>,
+}
+
+/// Implemented once per configured OAuth provider (Apple, a generic OIDC provider, ...) and
+/// driven by both the redirect-based flow and the device authorization flow.
+#[async_trait]
+pub trait OAuthProvider: Send + Sync {
+  fn name(&self) -> &'static str;
+  fn provider(&self) -> OAuthProviderId;
+  fn display_name(&self) -> &'static str;
+
+  /// Resolves the endpoints/credentials needed to start a flow. The generic OIDC provider
+  /// returns the endpoints from the `.well-known` document it fetched when it was constructed.
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError>;
+
+  fn oauth_scopes(&self) -> Vec<&'static str>;
+
+  /// Exchanges the tokens obtained from either the redirect or device flow for a TrailBase
+  /// user. `id_token` is populated whenever the token exchange returned one (most providers);
+  /// `nonce` is the value originally sent with the authorization request, when applicable, and
+  /// must match the id_token's `nonce` claim to guard against replay.
+  async fn get_user(
+    &self,
+    access_token: String,
+    id_token: Option<String>,
+    nonce: Option<String>,
+  ) -> Result<OAuthUser, AuthError>;
+}
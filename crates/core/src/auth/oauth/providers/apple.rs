@@ -1,6 +1,9 @@
 use async_trait::async_trait;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use lazy_static::lazy_static;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use url::Url;
 
 use crate::auth::AuthError;
@@ -8,9 +11,25 @@ use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
 use crate::auth::oauth::{OAuthClientSettings, OAuthProvider, OAuthUser};
 use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
 
+// Apple's "client secret" is not a static value but a short-lived ES256 JWT that we mint
+// ourselves from the `.p8` private key downloaded from the Apple developer portal. Apple
+// allows an expiry of at most six months; we use a much shorter TTL and regenerate lazily
+// so a stale secret never lingers.
+const CLIENT_SECRET_TTL: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+const CLIENT_SECRET_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+const JWKS_URL: &str = "https://appleid.apple.com/auth/keys";
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+const ISSUER: &str = "https://appleid.apple.com";
+
 pub(crate) struct AppleOAuthProvider {
   client_id: String,
-  client_secret: String,
+  team_id: String,
+  key_id: String,
+  encoding_key: EncodingKey,
+  // Cached (client_secret, not_after) so we don't mint a fresh JWT on every request.
+  cached_client_secret: Mutex<Option<(String, SystemTime)>>,
 }
 
 impl AppleOAuthProvider {
@@ -20,22 +39,35 @@ impl AppleOAuthProvider {
   const AUTH_URL: &'static str = "https://appleid.apple.com/auth/authorize";
   const TOKEN_URL: &'static str = "https://appleid.apple.com/auth/token";
   // Apple doesn't have a user api, but rather puts claims in the id token.
-  // const USER_API_URL: &'static str = "https://discord.com/api/users/@me";
   // jwksURL: "https://appleid.apple.com/auth/keys",
 
   fn new(config: &OAuthProviderConfig) -> Result<Self, OAuthProviderError> {
     let Some(client_id) = config.client_id.clone() else {
       return Err(OAuthProviderError::Missing("Apple client id".to_string()));
     };
-    let Some(client_secret) = config.client_secret.clone() else {
+    let Some(team_id) = config.team_id.clone() else {
+      return Err(OAuthProviderError::Missing("Apple team id".to_string()));
+    };
+    let Some(key_id) = config.key_id.clone() else {
+      return Err(OAuthProviderError::Missing("Apple key id".to_string()));
+    };
+    // The `client_secret` config field doubles as the PEM-encoded `.p8` EC private key used
+    // to sign the client-secret JWT, since Apple has no notion of a static client secret.
+    let Some(private_key_pem) = config.client_secret.clone() else {
       return Err(OAuthProviderError::Missing(
-        "Apple client secret".to_string(),
+        "Apple private key (.p8)".to_string(),
       ));
     };
 
+    let encoding_key = EncodingKey::from_ec_pem(private_key_pem.as_bytes())
+      .map_err(|err| OAuthProviderError::Missing(format!("invalid Apple private key: {err}")))?;
+
     return Ok(Self {
       client_id,
-      client_secret,
+      team_id,
+      key_id,
+      encoding_key,
+      cached_client_secret: Mutex::new(None),
     });
   }
 
@@ -45,10 +77,58 @@ impl AppleOAuthProvider {
       factory_name: Self::NAME,
       factory_display_name: Self::DISPLAY_NAME,
       factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
-        Ok(Box::new(Self::new(config)?))
+        let config = config.clone();
+        Box::pin(async move { Ok(Box::new(Self::new(&config)?) as Box<dyn OAuthProvider>) })
       }),
     }
   }
+
+  /// Returns a cached client-secret JWT, minting a new one if the cache is empty or close to
+  /// expiring.
+  fn client_secret(&self) -> Result<String, AuthError> {
+    let mut cached = self.cached_client_secret.lock().unwrap();
+    if let Some((secret, not_after)) = cached.as_ref() {
+      if *not_after > SystemTime::now() + CLIENT_SECRET_REFRESH_MARGIN {
+        return Ok(secret.clone());
+      }
+    }
+
+    let now = SystemTime::now();
+    let secret = self.mint_client_secret(now)?;
+    *cached = Some((secret.clone(), now + CLIENT_SECRET_TTL));
+
+    return Ok(secret);
+  }
+
+  fn mint_client_secret(&self, now: SystemTime) -> Result<String, AuthError> {
+    #[derive(serde::Serialize)]
+    struct Claims {
+      iss: String,
+      iat: u64,
+      exp: u64,
+      aud: &'static str,
+      sub: String,
+    }
+
+    let iat = now
+      .duration_since(UNIX_EPOCH)
+      .map_err(|err| AuthError::Internal(err.into()))?
+      .as_secs();
+
+    let claims = Claims {
+      iss: self.team_id.clone(),
+      iat,
+      exp: iat + CLIENT_SECRET_TTL.as_secs(),
+      aud: ISSUER,
+      sub: self.client_id.clone(),
+    };
+
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(self.key_id.clone());
+
+    return jsonwebtoken::encode(&header, &claims, &self.encoding_key)
+      .map_err(|err| AuthError::Internal(err.into()));
+  }
 }
 
 #[async_trait]
@@ -63,7 +143,7 @@ impl OAuthProvider for AppleOAuthProvider {
     Self::DISPLAY_NAME
   }
 
-  fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
     lazy_static! {
       static ref AUTH_URL: Url = Url::parse(AppleOAuthProvider::AUTH_URL).expect("infallible");
       static ref TOKEN_URL: Url = Url::parse(AppleOAuthProvider::TOKEN_URL).expect("infallible");
@@ -72,8 +152,10 @@ impl OAuthProvider for AppleOAuthProvider {
     return Ok(OAuthClientSettings {
       auth_url: AUTH_URL.clone(),
       token_url: TOKEN_URL.clone(),
+      // Apple has no device-authorization endpoint.
+      device_authorization_url: None,
       client_id: self.client_id.clone(),
-      client_secret: self.client_secret.clone(),
+      client_secret: self.client_secret()?,
     });
   }
 
@@ -81,57 +163,130 @@ impl OAuthProvider for AppleOAuthProvider {
     return vec!["name", "email"];
   }
 
-  async fn get_user(&self, access_token: String) -> Result<OAuthUser, AuthError> {
-    // TODO: Extract claims from token.
-
-    return Err(AuthError::Unauthorized);
-
-    // let response = reqwest::Client::new()
-    //   .get(Self::USER_API_URL)
-    //   .bearer_auth(access_token)
-    //   .send()
-    //   .await
-    //   .map_err(|err| AuthError::FailedDependency(err.into()))?;
-    //
-    // #[derive(Default, Deserialize, Debug)]
-    // struct AppleUser {
-    //   id: String,
-    //   email: String,
-    //   verified: bool,
-    //
-    //   // discriminator: Option<String>,
-    //   // username: Option<String>,
-    //   avatar: Option<String>,
-    // }
-    //
-    // let user = response
-    //   .json::<AppleUser>()
-    //   .await
-    //   .map_err(|err| AuthError::FailedDependency(err.into()))?;
-    // let verified = user.verified;
-    // if !verified {
-    //   return Err(AuthError::Unauthorized);
-    // }
-    //
-    // // let username = match (user.discriminator, user.username) {
-    // //   (Some(discriminator), Some(username)) => Some(format!("{username}#{discriminator}")),
-    // //   (None, Some(username)) => Some(username.to_string()),
-    // //   (Some(discriminator), None) => Some(discriminator.to_string()),
-    // //   (None, None) => None,
-    // // };
-    // let avatar = user.avatar.map(|avatar| {
-    //   format!(
-    //     "https://cdn.discordapp.com/avatars/{id}/{avatar}.png",
-    //     id = user.id
-    //   )
-    // });
-    //
-    // return Ok(OAuthUser {
-    //   provider_user_id: user.id,
-    //   provider_id: OAuthProviderId::Apple,
-    //   email: user.email,
-    //   verified: user.verified,
-    //   avatar,
-    // });
+  // NOTE: Apple has no userinfo endpoint, all identity claims live in the `id_token` returned
+  // alongside the access token by the token exchange, so the redirect-flow handler now threads
+  // it through here. When a `nonce` is given, it must match the id_token's `nonce` claim.
+  async fn get_user(
+    &self,
+    _access_token: String,
+    id_token: Option<String>,
+    nonce: Option<String>,
+  ) -> Result<OAuthUser, AuthError> {
+    let Some(id_token) = id_token else {
+      return Err(AuthError::Unauthorized);
+    };
+
+    let claims = verify_id_token(&id_token, &self.client_id).await?;
+    if nonce.is_some() && claims.nonce != nonce {
+      return Err(AuthError::Unauthorized);
+    }
+
+    // Hard stop rather than only gating first-link: Apple only omits/flips `email_verified` for
+    // private-relay or legacy migration cases, and we have no local record to fall back to here
+    // (this provider doesn't look up an existing user by `sub` before this check), so accepting
+    // an unverified email would let anyone claim an address they don't control.
+    if !claims.email_verified {
+      return Err(AuthError::Unauthorized);
+    }
+    let Some(email) = claims.email else {
+      return Err(AuthError::Unauthorized);
+    };
+
+    return Ok(OAuthUser {
+      provider_user_id: claims.sub,
+      provider_id: OAuthProviderId::Apple,
+      email,
+      verified: claims.email_verified,
+      avatar: None,
+    });
   }
 }
+
+#[derive(Debug, Deserialize)]
+struct AppleIdTokenClaims {
+  sub: String,
+  email: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_bool_or_string")]
+  email_verified: bool,
+  nonce: Option<String>,
+}
+
+/// Apple encodes `email_verified` as a JSON bool in the web flow but as the string "true"/"false"
+/// in some native flows, so we accept both.
+fn deserialize_bool_or_string<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum BoolOrString {
+    Bool(bool),
+    String(String),
+  }
+
+  return match BoolOrString::deserialize(deserializer)? {
+    BoolOrString::Bool(b) => Ok(b),
+    BoolOrString::String(s) => Ok(s == "true"),
+  };
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApplePublicKey {
+  kid: String,
+  n: String,
+  e: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApplePublicKeys {
+  keys: Vec<ApplePublicKey>,
+}
+
+async fn fetch_apple_jwks() -> Result<ApplePublicKeys, AuthError> {
+  lazy_static! {
+    static ref CACHE: Mutex<Option<(Instant, ApplePublicKeys)>> = Mutex::new(None);
+  }
+
+  if let Some((fetched_at, keys)) = CACHE.lock().unwrap().as_ref() {
+    if fetched_at.elapsed() < JWKS_CACHE_TTL {
+      return Ok(keys.clone());
+    }
+  }
+
+  let keys: ApplePublicKeys = reqwest::Client::new()
+    .get(JWKS_URL)
+    .send()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()))?
+    .json()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+  *CACHE.lock().unwrap() = Some((Instant::now(), keys.clone()));
+
+  return Ok(keys);
+}
+
+async fn verify_id_token(id_token: &str, client_id: &str) -> Result<AppleIdTokenClaims, AuthError> {
+  let header = jsonwebtoken::decode_header(id_token).map_err(|_| AuthError::Unauthorized)?;
+  let kid = header.kid.ok_or(AuthError::Unauthorized)?;
+
+  let jwks = fetch_apple_jwks().await?;
+  let key = jwks
+    .keys
+    .iter()
+    .find(|key| key.kid == kid)
+    .ok_or(AuthError::Unauthorized)?;
+
+  let decoding_key =
+    DecodingKey::from_rsa_components(&key.n, &key.e).map_err(|_| AuthError::Unauthorized)?;
+
+  let mut validation = Validation::new(Algorithm::RS256);
+  validation.set_audience(&[client_id]);
+  validation.set_issuer(&[ISSUER]);
+
+  let token_data = jsonwebtoken::decode::<AppleIdTokenClaims>(id_token, &decoding_key, &validation)
+    .map_err(|_| AuthError::Unauthorized)?;
+
+  return Ok(token_data.claims);
+}
@@ -0,0 +1,28 @@
+pub mod apple;
+pub mod oidc;
+
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::auth::oauth::OAuthProvider;
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum OAuthProviderError {
+  #[error("missing: {0}")]
+  Missing(String),
+}
+
+/// Boxed future returned by a provider factory. Boxed (rather than generic) so that factories
+/// for different provider types share one [`OAuthProviderFactory`] type.
+pub type FactoryFuture =
+  Pin<Box<dyn Future<Output = Result<Box<dyn OAuthProvider>, OAuthProviderError>> + Send>>;
+
+/// Describes how to construct a given provider type. Construction is async since some providers
+/// (e.g. generic OIDC) perform discovery requests before the instance is usable.
+pub struct OAuthProviderFactory {
+  pub id: OAuthProviderId,
+  pub factory_name: &'static str,
+  pub factory_display_name: &'static str,
+  pub factory: Box<dyn Fn(&str, &OAuthProviderConfig) -> FactoryFuture + Send + Sync>,
+}
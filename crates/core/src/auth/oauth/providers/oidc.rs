@@ -0,0 +1,323 @@
+use async_trait::async_trait;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::auth::AuthError;
+use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
+use crate::auth::oauth::{OAuthClientSettings, OAuthProvider, OAuthUser};
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Generic OpenID Connect provider configured from just an issuer ("authority") URL plus client
+/// credentials. Unlike the bespoke providers (Apple, Discord, ...) this one discovers all of its
+/// endpoints from `<authority>/.well-known/openid-configuration` once at startup, so it works
+/// against any compliant IdP (Keycloak, rauthy, Authentik, Google, ...) without a dedicated
+/// struct.
+pub(crate) struct OidcOAuthProvider {
+  // Leaked once in `new()`, not per-call: there may be several distinct OIDC providers
+  // configured side by side, each needing its own stable `&'static str` name.
+  name: &'static str,
+  display_name: &'static str,
+  client_id: String,
+  client_secret: String,
+  // Resolved once at startup and cached for the lifetime of the provider: the discovery
+  // document is server config, not per-request state.
+  discovery: OidcDiscoveryDocument,
+  jwks_cache: Mutex<Option<(Instant, OidcJwks)>>,
+}
+
+impl OidcOAuthProvider {
+  async fn new(name: &str, config: &OAuthProviderConfig) -> Result<Self, OAuthProviderError> {
+    let Some(authority) = config.authority.clone() else {
+      return Err(OAuthProviderError::Missing("OIDC authority url".to_string()));
+    };
+    let authority = Url::parse(&authority)
+      .map_err(|err| OAuthProviderError::Missing(format!("invalid OIDC authority url: {err}")))?;
+
+    let Some(client_id) = config.client_id.clone() else {
+      return Err(OAuthProviderError::Missing("OIDC client id".to_string()));
+    };
+    let Some(client_secret) = config.client_secret.clone() else {
+      return Err(OAuthProviderError::Missing("OIDC client secret".to_string()));
+    };
+
+    let discovery_url = authority
+      .join(".well-known/openid-configuration")
+      .map_err(|err| OAuthProviderError::Missing(format!("invalid OIDC authority url: {err}")))?;
+
+    // Async, like every other network call in this provider: provider construction runs on the
+    // same tokio runtime the server is already driving, and `reqwest::blocking` panics there.
+    let discovery: OidcDiscoveryDocument = reqwest::Client::new()
+      .get(discovery_url)
+      .send()
+      .await
+      .map_err(|err| OAuthProviderError::Missing(format!("OIDC discovery failed: {err}")))?
+      .json()
+      .await
+      .map_err(|err| OAuthProviderError::Missing(format!("OIDC discovery failed: {err}")))?;
+
+    let display_name = config
+      .display_name
+      .clone()
+      .unwrap_or_else(|| name.to_string());
+
+    return Ok(Self {
+      name: Box::leak(name.to_string().into_boxed_str()),
+      display_name: Box::leak(display_name.into_boxed_str()),
+      client_id,
+      client_secret,
+      discovery,
+      jwks_cache: Mutex::new(None),
+    });
+  }
+
+  pub fn factory() -> OAuthProviderFactory {
+    OAuthProviderFactory {
+      id: OAuthProviderId::Oidc,
+      factory_name: "oidc",
+      factory_display_name: "OpenID Connect",
+      factory: Box::new(|name: &str, config: &OAuthProviderConfig| {
+        let name = name.to_string();
+        let config = config.clone();
+        Box::pin(async move {
+          Ok(Box::new(Self::new(&name, &config).await?) as Box<dyn OAuthProvider>)
+        })
+      }),
+    }
+  }
+
+  async fn jwks(&self) -> Result<OidcJwks, AuthError> {
+    if let Some((fetched_at, jwks)) = self.jwks_cache.lock().unwrap().clone() {
+      if fetched_at.elapsed() < JWKS_CACHE_TTL {
+        return Ok(jwks);
+      }
+    }
+
+    let jwks: OidcJwks = reqwest::Client::new()
+      .get(&self.discovery.jwks_uri)
+      .send()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?
+      .json()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+    *self.jwks_cache.lock().unwrap() = Some((Instant::now(), jwks.clone()));
+
+    return Ok(jwks);
+  }
+
+  async fn verify_id_token(
+    &self,
+    id_token: &str,
+    expected_nonce: Option<&str>,
+  ) -> Result<OidcIdTokenClaims, AuthError> {
+    let jwks = self.jwks().await?;
+
+    let header = jsonwebtoken::decode_header(id_token).map_err(|_| AuthError::Unauthorized)?;
+    let kid = header.kid.ok_or(AuthError::Unauthorized)?;
+
+    let key = jwks
+      .keys
+      .iter()
+      .find(|key| key.kid == kid)
+      .ok_or(AuthError::Unauthorized)?;
+
+    let algorithm = algorithm_for_key(key)?;
+
+    let decoding_key = match key.kty.as_str() {
+      "RSA" => DecodingKey::from_rsa_components(&key.n, &key.e),
+      "EC" => DecodingKey::from_ec_components(&key.x, &key.y),
+      _ => return Err(AuthError::Unauthorized),
+    }
+    .map_err(|_| AuthError::Unauthorized)?;
+
+    let mut validation = Validation::new(algorithm);
+    validation.set_audience(&[self.client_id.clone()]);
+    validation.set_issuer(&[self.discovery.issuer.clone()]);
+
+    let token_data =
+      jsonwebtoken::decode::<OidcIdTokenClaims>(id_token, &decoding_key, &validation)
+        .map_err(|_| AuthError::Unauthorized)?;
+
+    let claims = token_data.claims;
+    if let Some(expected_nonce) = expected_nonce {
+      if claims.nonce.as_deref() != Some(expected_nonce) {
+        return Err(AuthError::Unauthorized);
+      }
+    }
+
+    return Ok(claims);
+  }
+
+  async fn userinfo(&self, access_token: &str) -> Result<OidcUserInfo, AuthError> {
+    let Some(userinfo_endpoint) = &self.discovery.userinfo_endpoint else {
+      return Err(AuthError::Unauthorized);
+    };
+
+    return reqwest::Client::new()
+      .get(userinfo_endpoint)
+      .bearer_auth(access_token)
+      .send()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?
+      .json::<OidcUserInfo>()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()));
+  }
+}
+
+#[async_trait]
+impl OAuthProvider for OidcOAuthProvider {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+  fn provider(&self) -> OAuthProviderId {
+    OAuthProviderId::Oidc
+  }
+  fn display_name(&self) -> &'static str {
+    self.display_name
+  }
+
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+    return Ok(OAuthClientSettings {
+      auth_url: Url::parse(&self.discovery.authorization_endpoint)
+        .map_err(|err| AuthError::Internal(err.into()))?,
+      token_url: Url::parse(&self.discovery.token_endpoint)
+        .map_err(|err| AuthError::Internal(err.into()))?,
+      // Not every IdP's discovery document advertises one.
+      device_authorization_url: self
+        .discovery
+        .device_authorization_endpoint
+        .as_deref()
+        .map(Url::parse)
+        .transpose()
+        .map_err(|err| AuthError::Internal(err.into()))?,
+      client_id: self.client_id.clone(),
+      client_secret: self.client_secret.clone(),
+    });
+  }
+
+  fn oauth_scopes(&self) -> Vec<&'static str> {
+    return vec!["openid", "email", "profile"];
+  }
+
+  async fn get_user(
+    &self,
+    access_token: String,
+    id_token: Option<String>,
+    nonce: Option<String>,
+  ) -> Result<OAuthUser, AuthError> {
+    let claims = match id_token {
+      Some(id_token) => Some(self.verify_id_token(&id_token, nonce.as_deref()).await?),
+      // Not every OIDC flow returns an id_token alongside the access token; fall back to the
+      // userinfo endpoint when it wasn't provided.
+      None => None,
+    };
+
+    let (provider_user_id, email, verified, avatar) = match claims {
+      Some(claims) if claims.email.is_some() => (
+        claims.sub,
+        claims.email.expect("checked above"),
+        claims.email_verified.unwrap_or(false),
+        claims.picture,
+      ),
+      Some(claims) => {
+        let info = self.userinfo(&access_token).await?;
+        (
+          claims.sub,
+          info.email.ok_or(AuthError::Unauthorized)?,
+          info.email_verified.unwrap_or(false),
+          info.picture.or(claims.picture),
+        )
+      }
+      None => {
+        let info = self.userinfo(&access_token).await?;
+        (
+          info.sub,
+          info.email.ok_or(AuthError::Unauthorized)?,
+          info.email_verified.unwrap_or(false),
+          info.picture,
+        )
+      }
+    };
+
+    return Ok(OAuthUser {
+      provider_user_id,
+      provider_id: OAuthProviderId::Oidc,
+      email,
+      verified,
+      avatar,
+    });
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OidcDiscoveryDocument {
+  issuer: String,
+  authorization_endpoint: String,
+  token_endpoint: String,
+  userinfo_endpoint: Option<String>,
+  jwks_uri: String,
+  device_authorization_endpoint: Option<String>,
+}
+
+/// Derives the verification algorithm for a JWKS key from its `kty`, falling back to the key's
+/// own `alg` only when it's present and agrees with `kty` — many IdPs (including real ones)
+/// omit `alg` on EC keys entirely, and trusting a mismatched `alg` would mint a decoding key for
+/// one algorithm while validating with another, hard-failing every token from that provider.
+fn algorithm_for_key(key: &OidcJwk) -> Result<Algorithm, AuthError> {
+  let from_kty = match key.kty.as_str() {
+    "RSA" => Algorithm::RS256,
+    "EC" => Algorithm::ES256,
+    _ => return Err(AuthError::Unauthorized),
+  };
+
+  return match key.alg.as_deref() {
+    None => Ok(from_kty),
+    Some("RS256") if from_kty == Algorithm::RS256 => Ok(Algorithm::RS256),
+    Some("ES256") if from_kty == Algorithm::ES256 => Ok(Algorithm::ES256),
+    Some(_) => Err(AuthError::Unauthorized),
+  };
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OidcJwk {
+  kid: String,
+  kty: String,
+  alg: Option<String>,
+  #[serde(default)]
+  n: String,
+  #[serde(default)]
+  e: String,
+  #[serde(default)]
+  x: String,
+  #[serde(default)]
+  y: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OidcJwks {
+  keys: Vec<OidcJwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcIdTokenClaims {
+  sub: String,
+  email: Option<String>,
+  email_verified: Option<bool>,
+  picture: Option<String>,
+  nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcUserInfo {
+  sub: String,
+  email: Option<String>,
+  email_verified: Option<bool>,
+  picture: Option<String>,
+}
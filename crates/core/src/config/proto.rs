@@ -0,0 +1,30 @@
+/// Identifies which [`crate::auth::oauth::OAuthProvider`] implementation a configured provider
+/// instance should use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthProviderId {
+  Apple,
+  Discord,
+  Github,
+  /// Generic OIDC providers are distinguished from one another by name, not by this id, since
+  /// there can be more than one configured at a time (Keycloak, rauthy, Authentik, ...).
+  Oidc,
+}
+
+/// Admin-configured settings for a single OAuth provider instance. Optional fields are only
+/// required by specific providers (e.g. `team_id`/`key_id` for Apple, `authority` for OIDC) and
+/// are validated by that provider's `new()`.
+#[derive(Debug, Clone, Default)]
+pub struct OAuthProviderConfig {
+  pub client_id: Option<String>,
+  pub client_secret: Option<String>,
+  pub display_name: Option<String>,
+
+  /// Apple: the Apple Developer Team ID, used as the `iss` claim of the client-secret JWT.
+  pub team_id: Option<String>,
+  /// Apple: the Sign in with Apple key id, used as the client-secret JWT's `kid` header.
+  pub key_id: Option<String>,
+
+  /// Generic OIDC: the issuer/authority url used to discover the rest of the endpoints from
+  /// `<authority>/.well-known/openid-configuration`.
+  pub authority: Option<String>,
+}